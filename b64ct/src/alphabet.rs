@@ -0,0 +1,706 @@
+//! Alphabets for "B64"-style encodings: a trait that parameterizes the
+//! branchless encode/decode primitives over the character set, plus the
+//! concrete alphabets used in practice (PHC, URL-safe, bcrypt, `crypt(3)`).
+
+use crate::{Error, InvalidLengthError};
+use core::str;
+
+#[cfg(feature = "alloc")]
+use crate::InvalidEncodingError;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+/// A "B64"-style alphabet: a 64-character subset of ASCII mapped to/from the
+/// 6-bit values `0..=63`, with the mapping computed by data-independent
+/// arithmetic rather than a lookup table.
+///
+/// Implementors only need to supply [`Alphabet::encode_6bits`] and
+/// [`Alphabet::decode_6bits`]; the whole-buffer `encode`/`decode` family is
+/// provided in terms of those two primitives.
+pub trait Alphabet: Sized {
+    /// Encode a 6-bit value (`0..=63`) as the alphabet's ASCII character.
+    fn encode_6bits(src: i16) -> u8;
+
+    /// Decode an ASCII character back into its 6-bit value (`0..=63`), or a
+    /// negative value if `src` isn't part of this alphabet.
+    fn decode_6bits(src: u8) -> i16;
+
+    /// Encode the input byte slice into this alphabet, writing the result
+    /// into the provided destination slice, and returning an ASCII-encoded
+    /// string value.
+    fn encode<'a>(src: &[u8], dst: &'a mut [u8]) -> Result<&'a str, InvalidLengthError> {
+        let elen = encoded_len(src);
+        if elen > dst.len() {
+            return Err(InvalidLengthError);
+        }
+        let dst = &mut dst[..elen];
+
+        let mut src_chunks = src.chunks_exact(3);
+        let mut dst_chunks = dst.chunks_exact_mut(4);
+        for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+            encode_3bytes::<Self>(s, d);
+        }
+        let src_rem = src_chunks.remainder();
+        let dst_rem = dst_chunks.into_remainder();
+
+        let mut tmp_in = [0u8; 3];
+        let mut tmp_out = [0u8; 4];
+        tmp_in[..src_rem.len()].copy_from_slice(src_rem);
+        encode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+        dst_rem.copy_from_slice(&tmp_out[..dst_rem.len()]);
+
+        debug_assert!(str::from_utf8(dst).is_ok());
+        // SAFETY: values written by `encode_3bytes` are valid one-byte UTF-8 chars
+        Ok(unsafe { str::from_utf8_unchecked(dst) })
+    }
+
+    /// Encode the input byte slice into this alphabet as a [`String`].
+    #[cfg(feature = "alloc")]
+    fn encode_string(input: &[u8]) -> String {
+        let elen = encoded_len(input);
+        let mut dst = vec![0u8; elen];
+        let res = Self::encode(input, &mut dst);
+        debug_assert_eq!(elen, res.unwrap().len());
+        debug_assert!(str::from_utf8(&dst).is_ok());
+        // SAFETY: `dst` is fully written and contains only valid one-byte UTF-8 chars
+        unsafe { String::from_utf8_unchecked(dst) }
+    }
+
+    /// Decode the given string of this alphabet's characters into the
+    /// provided destination buffer.
+    fn decode<'a>(src: &str, dst: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let dlen = decoded_len(src);
+        if dlen > dst.len() {
+            return Err(Error::InvalidLength);
+        }
+        let src = src.as_bytes();
+        let dst = &mut dst[..dlen];
+
+        let mut err: i16 = 0;
+
+        let mut src_chunks = src.chunks_exact(4);
+        let mut dst_chunks = dst.chunks_exact_mut(3);
+        for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+            err |= decode_3bytes::<Self>(s, d);
+        }
+        let src_rem = src_chunks.remainder();
+        let dst_rem = dst_chunks.into_remainder();
+
+        err |= !(src_rem.is_empty() || src_rem.len() >= 2) as i16;
+        let mut tmp_out = [0u8; 3];
+        let mut tmp_in = [b'A'; 4];
+        tmp_in[..src_rem.len()].copy_from_slice(src_rem);
+        err |= decode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+        dst_rem.copy_from_slice(&tmp_out[..dst_rem.len()]);
+
+        if err == 0 {
+            Ok(dst)
+        } else {
+            Err(Error::InvalidEncoding)
+        }
+    }
+
+    /// Decode this alphabet's encoded string into a byte vector.
+    #[cfg(feature = "alloc")]
+    fn decode_vec(input: &str) -> Result<Vec<u8>, InvalidEncodingError> {
+        let dlen = decoded_len(input);
+        let mut output = vec![0u8; dlen];
+        match Self::decode(input, &mut output) {
+            Ok(v) => debug_assert_eq!(dlen, v.len()),
+            Err(Error::InvalidEncoding) => return Err(InvalidEncodingError),
+            Err(Error::InvalidLength) => unreachable!(),
+        }
+        Ok(output)
+    }
+
+    /// Encode the input byte slice into this alphabet with RFC 4648 `=`
+    /// padding, writing the result into the provided destination slice.
+    fn encode_padded<'a>(src: &[u8], dst: &'a mut [u8]) -> Result<&'a str, InvalidLengthError> {
+        let elen = encoded_len_padded(src);
+        if elen > dst.len() {
+            return Err(InvalidLengthError);
+        }
+        let dst = &mut dst[..elen];
+
+        // Unlike the unpadded encoding, `dst` is always a whole number of
+        // 4-char groups here, so it has no `chunks_exact_mut(4)` remainder
+        // to hand the final (possibly short) source group to. Index the
+        // last group explicitly instead.
+        let full_groups = src.len() / 3;
+        for i in 0..full_groups {
+            encode_3bytes::<Self>(&src[i * 3..i * 3 + 3], &mut dst[i * 4..i * 4 + 4]);
+        }
+
+        let src_rem = &src[full_groups * 3..];
+        if !src_rem.is_empty() {
+            let mut tmp_in = [0u8; 3];
+            let mut tmp_out = [0u8; 4];
+            tmp_in[..src_rem.len()].copy_from_slice(src_rem);
+            encode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+
+            // A 1-byte remainder needs 2 `=`, a 2-byte remainder needs 1.
+            for c in tmp_out[src_rem.len() + 1..].iter_mut() {
+                *c = b'=';
+            }
+            dst[full_groups * 4..].copy_from_slice(&tmp_out);
+        }
+
+        debug_assert!(str::from_utf8(dst).is_ok());
+        // SAFETY: values written above are valid one-byte UTF-8 chars
+        Ok(unsafe { str::from_utf8_unchecked(dst) })
+    }
+
+    /// Decode an RFC 4648 `=`-padded, encoded string of this alphabet's
+    /// characters into the provided destination buffer.
+    fn decode_padded<'a>(src: &str, dst: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let src = src.as_bytes();
+        if !src.len().is_multiple_of(4) {
+            return Err(Error::InvalidLength);
+        }
+
+        // Count the `=` padding characters at the end up front: this only
+        // looks at the trailing run of `=` and the byte that ends it, never
+        // at the decoded value of any non-padding byte. A run of more than 2 is invalid
+        // padding, folded into the sticky error accumulator below rather
+        // than branched on directly; `body` still only ever drops the 2 that
+        // could be legitimate, so the rest of the decode stays in bounds.
+        let trailing_eq = src.iter().rev().take_while(|&&c| c == b'=').count();
+        let pad_len = trailing_eq.min(2);
+        let body = &src[..src.len() - pad_len];
+
+        let dlen = (body.len() * 3) / 4;
+        if dlen > dst.len() {
+            return Err(Error::InvalidLength);
+        }
+        let dst = &mut dst[..dlen];
+
+        let mut err: i16 = 0;
+        err |= (trailing_eq > 2) as i16;
+
+        let mut src_chunks = body.chunks_exact(4);
+        let mut dst_chunks = dst.chunks_exact_mut(3);
+        for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+            err |= decode_3bytes::<Self>(s, d);
+        }
+        let src_rem = src_chunks.remainder();
+        let dst_rem = dst_chunks.into_remainder();
+
+        err |= !(src_rem.is_empty() || src_rem.len() >= 2) as i16;
+        let mut tmp_out = [0u8; 3];
+        let mut tmp_in = [b'A'; 4];
+        tmp_in[..src_rem.len()].copy_from_slice(src_rem);
+        err |= decode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+        dst_rem.copy_from_slice(&tmp_out[..dst_rem.len()]);
+
+        if err == 0 {
+            Ok(dst)
+        } else {
+            Err(Error::InvalidEncoding)
+        }
+    }
+
+    /// Encode the input byte slice into this alphabet with RFC 4648 `=`
+    /// padding, inserting `ending` every `line_len` characters (e.g. 64 for
+    /// PEM/OpenSSH, 76 for MIME).
+    fn encode_wrapped<'a>(
+        src: &[u8],
+        line_len: usize,
+        ending: LineEnding,
+        dst: &'a mut [u8],
+    ) -> Result<&'a str, InvalidLengthError> {
+        if line_len == 0 {
+            return Err(InvalidLengthError);
+        }
+
+        let wlen = encoded_len_wrapped(src, line_len, ending);
+        if wlen > dst.len() {
+            return Err(InvalidLengthError);
+        }
+        let dst = &mut dst[..wlen];
+
+        let mut written = 0;
+        let mut col = 0;
+
+        for chunk in src.chunks(3) {
+            let mut out = [0u8; 4];
+            if chunk.len() == 3 {
+                encode_3bytes::<Self>(chunk, &mut out);
+            } else {
+                let mut tmp_in = [0u8; 3];
+                tmp_in[..chunk.len()].copy_from_slice(chunk);
+                encode_3bytes::<Self>(&tmp_in, &mut out);
+                for c in out[4 - (3 - chunk.len())..].iter_mut() {
+                    *c = b'=';
+                }
+            }
+
+            let mut idx = 0;
+            while idx < 4 {
+                if col == line_len {
+                    ending.write(&mut dst[written..written + ending.len()]);
+                    written += ending.len();
+                    col = 0;
+                }
+                let take = (line_len - col).min(4 - idx);
+                dst[written..written + take].copy_from_slice(&out[idx..idx + take]);
+                written += take;
+                col += take;
+                idx += take;
+            }
+        }
+
+        debug_assert_eq!(written, wlen);
+        debug_assert!(str::from_utf8(dst).is_ok());
+        // SAFETY: values written above are valid one-byte UTF-8 chars
+        Ok(unsafe { str::from_utf8_unchecked(dst) })
+    }
+
+    /// Decode a line-wrapped, `=`-padded encoded string of this alphabet's
+    /// characters into the provided destination buffer.
+    ///
+    /// `scratch` is used to compact the input (dropping `\r`/`\n` bytes)
+    /// ahead of the branchless decode loop, and must be at least as long as
+    /// `src`.
+    fn decode_wrapped<'d>(
+        src: &str,
+        scratch: &mut [u8],
+        dst: &'d mut [u8],
+    ) -> Result<&'d [u8], Error> {
+        let bytes = src.as_bytes();
+        if bytes.len() > scratch.len() {
+            return Err(Error::InvalidLength);
+        }
+
+        // Compact the input into `scratch`, dropping CR/LF bytes, before the
+        // data-independent decode runs below. Skipping whitespace here
+        // rather than inside `decode_3bytes` keeps that hot loop free of
+        // data-dependent branches over the payload itself.
+        let mut n = 0;
+        for &b in bytes {
+            if b != b'\r' && b != b'\n' {
+                scratch[n] = b;
+                n += 1;
+            }
+        }
+
+        debug_assert!(str::from_utf8(&scratch[..n]).is_ok());
+        // SAFETY: `scratch[..n]` is a subsequence of the valid UTF-8 `src`
+        // with only ASCII CR/LF bytes removed
+        let compact = unsafe { str::from_utf8_unchecked(&scratch[..n]) };
+        Self::decode_padded(compact, dst)
+    }
+
+    /// Decode a "B64" string living in `buf` back into the front of that
+    /// same buffer, avoiding a second destination allocation.
+    ///
+    /// Since every 4 input characters map to 3 output bytes, the decoded
+    /// output read left-to-right never overtakes the as-yet-undecoded input,
+    /// so this can run entirely in place.
+    fn decode_in_place(buf: &mut [u8]) -> Result<&[u8], Error> {
+        let dlen = (buf.len() * 3) / 4;
+        let mut err: i16 = 0;
+        let mut src_pos = 0;
+        let mut dst_pos = 0;
+
+        while src_pos + 4 <= buf.len() {
+            let tmp_in = [
+                buf[src_pos],
+                buf[src_pos + 1],
+                buf[src_pos + 2],
+                buf[src_pos + 3],
+            ];
+            let mut tmp_out = [0u8; 3];
+            err |= decode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+            buf[dst_pos..dst_pos + 3].copy_from_slice(&tmp_out);
+            src_pos += 4;
+            dst_pos += 3;
+        }
+
+        let rem = buf.len() - src_pos;
+        err |= !(rem == 0 || rem >= 2) as i16;
+        let mut tmp_in = [b'A'; 4];
+        tmp_in[..rem].copy_from_slice(&buf[src_pos..src_pos + rem]);
+        let mut tmp_out = [0u8; 3];
+        err |= decode_3bytes::<Self>(&tmp_in, &mut tmp_out);
+        let out_rem = dlen - dst_pos;
+        buf[dst_pos..dst_pos + out_rem].copy_from_slice(&tmp_out[..out_rem]);
+
+        if err == 0 {
+            Ok(&buf[..dlen])
+        } else {
+            Err(Error::InvalidEncoding)
+        }
+    }
+}
+
+/// Line ending inserted by [`Alphabet::encode_wrapped`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    const fn len(self) -> usize {
+        match self {
+            LineEnding::Lf => 1,
+            LineEnding::Crlf => 2,
+        }
+    }
+
+    fn write(self, dst: &mut [u8]) {
+        match self {
+            LineEnding::Lf => dst[0] = b'\n',
+            LineEnding::Crlf => {
+                dst[0] = b'\r';
+                dst[1] = b'\n';
+            }
+        }
+    }
+}
+
+/// Get the line-wrapped, `=`-padded encoded length of the given byte slice:
+/// [`encoded_len_padded`] plus one `ending` for every full `line_len`-char
+/// line except the last.
+pub(crate) const fn encoded_len_wrapped(bytes: &[u8], line_len: usize, ending: LineEnding) -> usize {
+    let elen = encoded_len_padded(bytes);
+    if elen == 0 || line_len == 0 {
+        return elen;
+    }
+    let full_lines = (elen - 1) / line_len;
+    elen + full_lines * ending.len()
+}
+
+/// Get the encoded length of the given byte slice for any [`Alphabet`].
+pub(crate) const fn encoded_len(bytes: &[u8]) -> usize {
+    let q = bytes.len() * 4;
+    let r = q % 3;
+    (q / 3) + (r != 0) as usize
+}
+
+/// Get the decoded length of the given encoded string for any [`Alphabet`].
+pub(crate) const fn decoded_len(bytes: &str) -> usize {
+    (bytes.len() * 3) / 4
+}
+
+/// Get the `=`-padded encoded length of the given byte slice for any
+/// [`Alphabet`]: unlike [`encoded_len`], this always rounds up to a multiple
+/// of 4 characters.
+pub(crate) const fn encoded_len_padded(bytes: &[u8]) -> usize {
+    bytes.len().div_ceil(3) * 4
+}
+
+#[inline(always)]
+pub(crate) fn encode_3bytes<A: Alphabet>(src: &[u8], dst: &mut [u8]) {
+    debug_assert_eq!(src.len(), 3);
+    debug_assert!(dst.len() >= 4, "dst too short: {}", dst.len());
+
+    let b0 = src[0] as i16;
+    let b1 = src[1] as i16;
+    let b2 = src[2] as i16;
+
+    dst[0] = A::encode_6bits(b0 >> 2);
+    dst[1] = A::encode_6bits(((b0 << 4) | (b1 >> 4)) & 63);
+    dst[2] = A::encode_6bits(((b1 << 2) | (b2 >> 6)) & 63);
+    dst[3] = A::encode_6bits(b2 & 63);
+}
+
+#[inline(always)]
+pub(crate) fn decode_3bytes<A: Alphabet>(src: &[u8], dst: &mut [u8]) -> i16 {
+    debug_assert_eq!(src.len(), 4);
+    debug_assert!(dst.len() >= 3, "dst too short: {}", dst.len());
+
+    let c0 = A::decode_6bits(src[0]);
+    let c1 = A::decode_6bits(src[1]);
+    let c2 = A::decode_6bits(src[2]);
+    let c3 = A::decode_6bits(src[3]);
+
+    dst[0] = ((c0 << 2) | (c1 >> 4)) as u8;
+    dst[1] = ((c1 << 4) | (c2 >> 2)) as u8;
+    dst[2] = ((c2 << 6) | c3) as u8;
+
+    ((c0 | c1 | c2 | c3) >> 8) & 1
+}
+
+// B64 character set (PHC string format):
+// [A-Z]      [a-z]      [0-9]      +     /
+// 0x41-0x5a, 0x61-0x7a, 0x30-0x39, 0x2b, 0x2f
+
+/// Standard "B64" alphabet used by the PHC string format: `[A-Z]`, `[a-z]`,
+/// `[0-9]`, `+`, `/`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Std;
+
+impl Alphabet for Std {
+    #[inline(always)]
+    fn encode_6bits(src: i16) -> u8 {
+        let mut diff = 0x41i16;
+
+        // if (in > 25) diff += 0x61 - 0x41 - 26; // 6
+        diff += ((25i16 - src) >> 8) & 6;
+
+        // if (in > 51) diff += 0x30 - 0x61 - 26; // -75
+        diff -= ((51i16 - src) >> 8) & 75;
+
+        // if (in > 61) diff += 0x2b - 0x30 - 10; // -15
+        diff -= ((61i16 - src) >> 8) & 15;
+
+        // if (in > 62) diff += 0x2f - 0x2b - 1; // 3
+        diff += ((62i16 - src) >> 8) & 3;
+
+        (src + diff) as u8
+    }
+
+    #[inline(always)]
+    fn decode_6bits(src: u8) -> i16 {
+        let ch = src as i16;
+        let mut ret: i16 = -1;
+
+        // if (ch > 0x40 && ch < 0x5b) ret += ch - 0x41 + 1; // -64
+        ret += (((64i16 - ch) & (ch - 91i16)) >> 8) & (ch - 64i16);
+
+        // if (ch > 0x60 && ch < 0x7b) ret += ch - 0x61 + 26 + 1; // -70
+        ret += (((96i16 - ch) & (ch - 123i16)) >> 8) & (ch - 70i16);
+
+        // if (ch > 0x2f && ch < 0x3a) ret += ch - 0x30 + 52 + 1; // 5
+        ret += (((47i16 - ch) & (ch - 58i16)) >> 8) & (ch + 5i16);
+
+        // if (ch == 0x2b) ret += 62 + 1;
+        ret += (((42i16 - ch) & (ch - 44i16)) >> 8) & 63;
+
+        // if (ch == 0x2f) ret += 63 + 1;
+        ret + ((((46i16 - ch) & (ch - 48i16)) >> 8) & 64)
+    }
+}
+
+/// URL-safe "B64"/Base64 alphabet: `[A-Z]`, `[a-z]`, `[0-9]`, `-`, `_`
+/// (RFC 4648 §5).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UrlSafe;
+
+impl Alphabet for UrlSafe {
+    #[inline(always)]
+    fn encode_6bits(src: i16) -> u8 {
+        let mut diff = 0x41i16;
+
+        // if (in > 25) diff += 0x61 - 0x41 - 26; // 6
+        diff += ((25i16 - src) >> 8) & 6;
+
+        // if (in > 51) diff += 0x30 - 0x61 - 26; // -75
+        diff -= ((51i16 - src) >> 8) & 75;
+
+        // if (in > 61) diff += 0x2d - 0x30 - 10; // -13
+        diff -= ((61i16 - src) >> 8) & 13;
+
+        // if (in > 62) diff += 0x5f - 0x2d - 1; // 49
+        diff += ((62i16 - src) >> 8) & 49;
+
+        (src + diff) as u8
+    }
+
+    #[inline(always)]
+    fn decode_6bits(src: u8) -> i16 {
+        let ch = src as i16;
+        let mut ret: i16 = -1;
+
+        // if (ch > 0x40 && ch < 0x5b) ret += ch - 0x41 + 1; // -64
+        ret += (((64i16 - ch) & (ch - 91i16)) >> 8) & (ch - 64i16);
+
+        // if (ch > 0x60 && ch < 0x7b) ret += ch - 0x61 + 26 + 1; // -70
+        ret += (((96i16 - ch) & (ch - 123i16)) >> 8) & (ch - 70i16);
+
+        // if (ch > 0x2f && ch < 0x3a) ret += ch - 0x30 + 52 + 1; // 5
+        ret += (((47i16 - ch) & (ch - 58i16)) >> 8) & (ch + 5i16);
+
+        // if (ch == 0x2d) ret += 62 + 1;
+        ret += (((44i16 - ch) & (ch - 46i16)) >> 8) & 63;
+
+        // if (ch == 0x5f) ret += 63 + 1;
+        ret + ((((94i16 - ch) & (ch - 96i16)) >> 8) & 64)
+    }
+}
+
+/// bcrypt's "B64" alphabet: `.`, `/`, `[A-Z]`, `[a-z]`, `[0-9]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bcrypt;
+
+impl Alphabet for Bcrypt {
+    #[inline(always)]
+    fn encode_6bits(src: i16) -> u8 {
+        let mut diff = 0x2ei16;
+
+        // if (in > 1) diff += 0x41 - 0x2e - 2; // 17
+        diff += ((1i16 - src) >> 8) & 17;
+
+        // if (in > 27) diff += 0x61 - 0x41 - 26; // 6
+        diff += ((27i16 - src) >> 8) & 6;
+
+        // if (in > 53) diff += 0x30 - 0x61 - 26; // -75
+        diff -= ((53i16 - src) >> 8) & 75;
+
+        (src + diff) as u8
+    }
+
+    #[inline(always)]
+    fn decode_6bits(src: u8) -> i16 {
+        let ch = src as i16;
+        let mut ret: i16 = -1;
+
+        // if (ch == 0x2e) ret += 0 + 1;
+        ret += (((45i16 - ch) & (ch - 47i16)) >> 8) & 1;
+
+        // if (ch == 0x2f) ret += 1 + 1;
+        ret += (((46i16 - ch) & (ch - 48i16)) >> 8) & 2;
+
+        // if (ch > 0x40 && ch < 0x5b) ret += ch - 0x41 + 2 + 1;
+        ret += (((64i16 - ch) & (ch - 91i16)) >> 8) & (ch - 62i16);
+
+        // if (ch > 0x60 && ch < 0x7b) ret += ch - 0x61 + 28 + 1;
+        ret += (((96i16 - ch) & (ch - 123i16)) >> 8) & (ch - 68i16);
+
+        // if (ch > 0x2f && ch < 0x3a) ret += ch - 0x30 + 54 + 1;
+        ret + ((((47i16 - ch) & (ch - 58i16)) >> 8) & (ch + 7i16))
+    }
+}
+
+/// `crypt(3)`/shacrypt "B64" alphabet: `.`, `/`, `[0-9]`, `[A-Z]`, `[a-z]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Crypt;
+
+impl Alphabet for Crypt {
+    #[inline(always)]
+    fn encode_6bits(src: i16) -> u8 {
+        let mut diff = 0x2ei16;
+
+        // if (in > 11) diff += 0x41 - 0x2e - 12; // 7
+        diff += ((11i16 - src) >> 8) & 7;
+
+        // if (in > 37) diff += 0x61 - 0x41 - 26; // 6
+        diff += ((37i16 - src) >> 8) & 6;
+
+        (src + diff) as u8
+    }
+
+    #[inline(always)]
+    fn decode_6bits(src: u8) -> i16 {
+        let ch = src as i16;
+        let mut ret: i16 = -1;
+
+        // if (ch == 0x2e) ret += 0 + 1;
+        ret += (((45i16 - ch) & (ch - 47i16)) >> 8) & 1;
+
+        // if (ch == 0x2f) ret += 1 + 1;
+        ret += (((46i16 - ch) & (ch - 48i16)) >> 8) & 2;
+
+        // if (ch > 0x2f && ch < 0x3a) ret += ch - 0x30 + 2 + 1;
+        ret += (((47i16 - ch) & (ch - 58i16)) >> 8) & (ch - 45i16);
+
+        // if (ch > 0x40 && ch < 0x5b) ret += ch - 0x41 + 12 + 1;
+        ret += (((64i16 - ch) & (ch - 91i16)) >> 8) & (ch - 52i16);
+
+        // if (ch > 0x60 && ch < 0x7b) ret += ch - 0x61 + 38 + 1;
+        ret + ((((96i16 - ch) & (ch - 123i16)) >> 8) & (ch - 58i16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn roundtrip<A: Alphabet>(input: &[u8]) {
+        let mut enc_buf = [0u8; 64];
+        let encoded = A::encode(input, &mut enc_buf).unwrap();
+        let mut dec_buf = [0u8; 64];
+        let decoded = A::decode(encoded, &mut dec_buf).unwrap();
+        assert_eq!(decoded, input);
+    }
+
+    #[test]
+    fn roundtrip_all_alphabets() {
+        for len in 0..=DATA.len() {
+            let input = &DATA[..len];
+            roundtrip::<Std>(input);
+            roundtrip::<UrlSafe>(input);
+            roundtrip::<Bcrypt>(input);
+            roundtrip::<Crypt>(input);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_invalid_char() {
+        let mut dst = [0u8; 8];
+        assert!(Std::decode("A!==", &mut dst).is_err());
+    }
+
+    #[test]
+    fn encode_rejects_undersized_dst() {
+        let mut dst = [0u8; 1];
+        assert!(Std::encode(&[1, 2, 3], &mut dst).is_err());
+    }
+
+    fn roundtrip_padded<A: Alphabet>(input: &[u8]) {
+        let mut enc_buf = [0u8; 64];
+        let encoded = A::encode_padded(input, &mut enc_buf).unwrap();
+        assert_eq!(encoded.len() % 4, 0);
+        let mut dec_buf = [0u8; 64];
+        let decoded = A::decode_padded(encoded, &mut dec_buf).unwrap();
+        assert_eq!(decoded, input);
+    }
+
+    #[test]
+    fn roundtrip_padded_all_alphabets() {
+        for len in 0..=DATA.len() {
+            let input = &DATA[..len];
+            roundtrip_padded::<Std>(input);
+            roundtrip_padded::<UrlSafe>(input);
+            roundtrip_padded::<Bcrypt>(input);
+            roundtrip_padded::<Crypt>(input);
+        }
+    }
+
+    #[test]
+    fn decode_padded_rejects_bad_length() {
+        let mut dst = [0u8; 8];
+        assert!(Std::decode_padded("AAA", &mut dst).is_err());
+    }
+
+    #[test]
+    fn decode_padded_rejects_excess_padding() {
+        let mut dst = [0u8; 8];
+        assert!(Std::decode_padded("AA======", &mut dst).is_err());
+    }
+
+    #[test]
+    fn encode_wrapped_rejects_zero_line_len() {
+        let mut dst = [0u8; 64];
+        assert!(Std::encode_wrapped(&[1, 2, 3], 0, LineEnding::Lf, &mut dst).is_err());
+    }
+
+    #[test]
+    fn roundtrip_wrapped() {
+        let input: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        for &ending in &[LineEnding::Lf, LineEnding::Crlf] {
+            for &line_len in &[1usize, 2, 3, 4, 5, 64] {
+                let mut enc_buf = [0u8; 128];
+                let encoded = Std::encode_wrapped(&input, line_len, ending, &mut enc_buf).unwrap();
+                let mut scratch = [0u8; 128];
+                let mut dst = [0u8; 16];
+                let decoded = Std::decode_wrapped(encoded, &mut scratch, &mut dst).unwrap();
+                assert_eq!(decoded, &input);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_in_place() {
+        let input: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buf = [0u8; 32];
+        let encoded_len = Std::encode(&input, &mut buf).unwrap().len();
+        let decoded = Std::decode_in_place(&mut buf[..encoded_len]).unwrap();
+        assert_eq!(decoded, &input);
+    }
+}
@@ -0,0 +1,196 @@
+//! Constant-time hexadecimal encoding, built with the same data-independent
+//! arithmetic discipline as the "B64" codec in the crate root.
+//!
+//! Decoding accepts either case; encoding is offered in both lowercase
+//! (`encode`/`encode_string`) and uppercase (`encode_upper`/
+//! `encode_upper_string`) forms.
+
+use crate::{Error, InvalidLengthError};
+use core::str;
+
+#[cfg(feature = "alloc")]
+use crate::InvalidEncodingError;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+/// Encode the input byte slice as lowercase hexadecimal, writing the result
+/// into the provided destination slice, and returning an ASCII-encoded
+/// string value.
+pub fn encode<'a>(src: &[u8], dst: &'a mut [u8]) -> Result<&'a str, InvalidLengthError> {
+    encode_with_case(src, dst, false)
+}
+
+/// Encode the input byte slice as uppercase hexadecimal.
+pub fn encode_upper<'a>(src: &[u8], dst: &'a mut [u8]) -> Result<&'a str, InvalidLengthError> {
+    encode_with_case(src, dst, true)
+}
+
+/// Encode the input byte slice as a lowercase hexadecimal [`String`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub fn encode_string(input: &[u8]) -> String {
+    let mut dst = vec![0u8; encoded_len(input)];
+    encode(input, &mut dst).unwrap();
+    debug_assert!(str::from_utf8(&dst).is_ok());
+    // SAFETY: `dst` is fully written and contains only valid one-byte UTF-8 chars
+    unsafe { String::from_utf8_unchecked(dst) }
+}
+
+/// Encode the input byte slice as an uppercase hexadecimal [`String`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub fn encode_upper_string(input: &[u8]) -> String {
+    let mut dst = vec![0u8; encoded_len(input)];
+    encode_upper(input, &mut dst).unwrap();
+    debug_assert!(str::from_utf8(&dst).is_ok());
+    // SAFETY: `dst` is fully written and contains only valid one-byte UTF-8 chars
+    unsafe { String::from_utf8_unchecked(dst) }
+}
+
+/// Get the hex-encoded length of the given byte slice.
+pub const fn encoded_len(bytes: &[u8]) -> usize {
+    bytes.len() * 2
+}
+
+/// Hex-decode the given source string into the provided destination buffer.
+///
+/// Accepts both lowercase and uppercase hex digits.
+pub fn decode<'a>(src: &str, dst: &'a mut [u8]) -> Result<&'a [u8], Error> {
+    let dlen = decoded_len(src);
+    if dlen > dst.len() {
+        return Err(Error::InvalidLength);
+    }
+    let dst = &mut dst[..dlen];
+    let src = src.as_bytes();
+
+    let mut err: i16 = 0;
+    err |= !src.len().is_multiple_of(2) as i16;
+
+    for (pair, out) in src.chunks_exact(2).zip(dst.iter_mut()) {
+        let hi = decode_nibble(pair[0]);
+        let lo = decode_nibble(pair[1]);
+        *out = ((hi << 4) | lo) as u8;
+        err |= (hi | lo) >> 8 & 1;
+    }
+
+    if err == 0 {
+        Ok(dst)
+    } else {
+        Err(Error::InvalidEncoding)
+    }
+}
+
+/// Hex-decode the given source string into a byte vector.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub fn decode_vec(input: &str) -> Result<Vec<u8>, InvalidEncodingError> {
+    let dlen = decoded_len(input);
+    let mut output = vec![0u8; dlen];
+    match decode(input, &mut output) {
+        Ok(v) => debug_assert_eq!(dlen, v.len()),
+        Err(Error::InvalidEncoding) => return Err(InvalidEncodingError),
+        Err(Error::InvalidLength) => unreachable!(),
+    }
+    Ok(output)
+}
+
+/// Get the length of the output from decoding the provided hex-encoded
+/// input.
+pub const fn decoded_len(bytes: &str) -> usize {
+    bytes.len() / 2
+}
+
+#[inline(always)]
+fn encode_with_case<'a>(
+    src: &[u8],
+    dst: &'a mut [u8],
+    upper: bool,
+) -> Result<&'a str, InvalidLengthError> {
+    let elen = encoded_len(src);
+    if elen > dst.len() {
+        return Err(InvalidLengthError);
+    }
+    let dst = &mut dst[..elen];
+
+    for (byte, pair) in src.iter().zip(dst.chunks_exact_mut(2)) {
+        pair[0] = encode_nibble((byte >> 4) as i16, upper);
+        pair[1] = encode_nibble((byte & 0xf) as i16, upper);
+    }
+
+    debug_assert!(str::from_utf8(dst).is_ok());
+    // SAFETY: values written by `encode_nibble` are valid one-byte UTF-8 chars
+    Ok(unsafe { str::from_utf8_unchecked(dst) })
+}
+
+#[inline(always)]
+fn encode_nibble(nibble: i16, upper: bool) -> u8 {
+    let alpha_offset = if upper {
+        (b'A' - b'0' - 10) as i16
+    } else {
+        (b'a' - b'0' - 10) as i16
+    };
+
+    // if (nibble > 9) diff += alpha_offset;
+    let diff = ((9i16 - nibble) >> 8) & alpha_offset;
+
+    (nibble + b'0' as i16 + diff) as u8
+}
+
+#[inline(always)]
+fn decode_nibble(src: u8) -> i16 {
+    let ch = src as i16;
+    let mut ret: i16 = -1;
+
+    // if (ch > 0x2f && ch < 0x3a) ret += ch - 0x30 + 1; // -47
+    ret += (((47i16 - ch) & (ch - 58i16)) >> 8) & (ch - 47i16);
+
+    // if (ch > 0x60 && ch < 0x67) ret += ch - 0x61 + 10 + 1; // -86
+    ret += (((96i16 - ch) & (ch - 103i16)) >> 8) & (ch - 86i16);
+
+    // if (ch > 0x40 && ch < 0x47) ret += ch - 0x41 + 10 + 1; // -54
+    ret + ((((64i16 - ch) & (ch - 71i16)) >> 8) & (ch - 54i16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let data: [u8; 8] = [0x00, 0x01, 0x0f, 0x10, 0x7f, 0x80, 0xfe, 0xff];
+        let mut enc_buf = [0u8; 16];
+        let encoded = encode(&data, &mut enc_buf).unwrap();
+        let mut dec_buf = [0u8; 8];
+        let decoded = decode(encoded, &mut dec_buf).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn decode_accepts_uppercase_and_lowercase() {
+        let mut dst_lower = [0u8; 2];
+        let mut dst_upper = [0u8; 2];
+        assert_eq!(
+            decode("dead", &mut dst_lower).unwrap(),
+            decode("DEAD", &mut dst_upper).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_rejects_odd_length() {
+        let mut dst = [0u8; 4];
+        assert!(decode("abc", &mut dst).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_invalid_char() {
+        let mut dst = [0u8; 4];
+        assert!(decode("zz", &mut dst).is_err());
+    }
+
+    #[test]
+    fn encode_upper_matches_case() {
+        let mut dst = [0u8; 4];
+        let encoded = encode_upper(&[0xab, 0xcd], &mut dst).unwrap();
+        assert_eq!(encoded, "ABCD");
+    }
+}
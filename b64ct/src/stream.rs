@@ -0,0 +1,316 @@
+//! Streaming, incremental "B64" encoding and decoding for inputs too large
+//! to hold in memory at once, such as files or socket streams.
+//!
+//! [`Encoder`] and [`Decoder`] buffer the 0-2 (encoding) or 0-3 (decoding)
+//! leftover bytes between calls, so callers can feed or request data in
+//! whatever chunk sizes are convenient.
+
+use crate::alphabet::{decode_3bytes, encode_3bytes, encoded_len};
+use crate::{Alphabet, Error, Std};
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
+use std::io;
+
+/// A destination that receives already-"B64"-encoded ASCII, written
+/// incrementally by an [`Encoder`].
+///
+/// Implemented for every [`std::io::Write`] when the `std` feature is
+/// enabled; `no_std` callers can implement it for their own buffers.
+pub trait Sink {
+    /// Write `encoded` to this sink.
+    fn write_encoded(&mut self, encoded: &[u8]) -> Result<(), Error>;
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Sink for W {
+    fn write_encoded(&mut self, encoded: &[u8]) -> Result<(), Error> {
+        self.write_all(encoded).map_err(|_| Error::InvalidEncoding)
+    }
+}
+
+/// A source of "B64"-encoded ASCII bytes, pulled incrementally by a
+/// [`Decoder`].
+///
+/// Implemented for every [`std::io::Read`] when the `std` feature is
+/// enabled; `no_std` callers can implement it for their own buffers.
+pub trait Source {
+    /// Pull more encoded bytes into `buf`, returning how many were read.
+    /// `Ok(0)` signals end of input.
+    fn read_encoded(&mut self, buf: &mut [u8]) -> Result<usize, Error>;
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Source for R {
+    fn read_encoded(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.read(buf).map_err(|_| Error::InvalidEncoding)
+    }
+}
+
+/// Incremental "B64" encoder.
+///
+/// Buffers input into 3-byte blocks, writing each encoded 4-character group
+/// to the wrapped [`Sink`] as it fills. Call [`Encoder::finish`] to flush
+/// the final short group, if any.
+pub struct Encoder<'w, W: Sink, A: Alphabet = Std> {
+    sink: &'w mut W,
+    buf: [u8; 3],
+    buf_len: usize,
+    _alphabet: PhantomData<A>,
+}
+
+impl<'w, W: Sink, A: Alphabet> Encoder<'w, W, A> {
+    /// Wrap `sink`, ready to receive input via [`Encoder::update`].
+    pub fn new(sink: &'w mut W) -> Self {
+        Self {
+            sink,
+            buf: [0u8; 3],
+            buf_len: 0,
+            _alphabet: PhantomData,
+        }
+    }
+
+    /// Feed more raw bytes into the encoder, writing every complete encoded
+    /// group to the wrapped sink and buffering the 0-2 byte remainder.
+    pub fn update(&mut self, mut input: &[u8]) -> Result<(), Error> {
+        if self.buf_len > 0 {
+            let take = (3 - self.buf_len).min(input.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&input[..take]);
+            self.buf_len += take;
+            input = &input[take..];
+
+            if self.buf_len < 3 {
+                return Ok(());
+            }
+
+            let mut out = [0u8; 4];
+            encode_3bytes::<A>(&self.buf, &mut out);
+            self.sink.write_encoded(&out)?;
+            self.buf_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(3);
+        for chunk in &mut chunks {
+            let mut out = [0u8; 4];
+            encode_3bytes::<A>(chunk, &mut out);
+            self.sink.write_encoded(&out)?;
+        }
+
+        let rem = chunks.remainder();
+        self.buf[..rem.len()].copy_from_slice(rem);
+        self.buf_len = rem.len();
+
+        Ok(())
+    }
+
+    /// Flush any buffered trailing bytes as a short final group, and
+    /// consume the encoder.
+    pub fn finish(self) -> Result<(), Error> {
+        if self.buf_len > 0 {
+            let leftover = self.buf_len;
+            let mut tmp_in = [0u8; 3];
+            tmp_in[..leftover].copy_from_slice(&self.buf[..leftover]);
+            let mut out = [0u8; 4];
+            encode_3bytes::<A>(&tmp_in, &mut out);
+            let elen = encoded_len(&self.buf[..leftover]);
+            self.sink.write_encoded(&out[..elen])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w, W: Sink, A: Alphabet> io::Write for Encoder<'w, W, A> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "B64 encoder sink write failed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Incremental "B64" decoder.
+///
+/// Pulls encoded ASCII from the wrapped [`Source`] and decodes it in
+/// 4-character blocks, reading from the source until a quad is complete (or
+/// the source is exhausted) before decoding it. A decoded group that doesn't fit in the caller's `out` buffer all
+/// at once is handed out a few bytes at a time across multiple calls, so
+/// `out` may be as small as a single byte. An invalid character anywhere in
+/// the stream sets a sticky error that is surfaced on every call from that
+/// point on.
+pub struct Decoder<'r, R: Source, A: Alphabet = Std> {
+    source: &'r mut R,
+    in_buf: [u8; 4],
+    in_len: usize,
+    out_buf: [u8; 3],
+    out_len: usize,
+    out_pos: usize,
+    eof: bool,
+    err: i16,
+    _alphabet: PhantomData<A>,
+}
+
+impl<'r, R: Source, A: Alphabet> Decoder<'r, R, A> {
+    /// Wrap `source`, ready to produce decoded bytes via [`Decoder::decode`].
+    pub fn new(source: &'r mut R) -> Self {
+        Self {
+            source,
+            in_buf: [0u8; 4],
+            in_len: 0,
+            out_buf: [0u8; 3],
+            out_len: 0,
+            out_pos: 0,
+            eof: false,
+            err: 0,
+            _alphabet: PhantomData,
+        }
+    }
+
+    /// Decode up to `out.len()` bytes into `out`, pulling more encoded input
+    /// from the wrapped source as needed.
+    ///
+    /// Returns the number of bytes written, or `0` once the source is
+    /// exhausted and every decoded byte has been delivered. Never returns
+    /// `Ok(0)` merely because `out` is too small to hold a whole decoded
+    /// group.
+    pub fn decode(&mut self, out: &mut [u8]) -> Result<usize, Error> {
+        if self.err != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        if self.out_pos == self.out_len {
+            self.fill_group()?;
+            if self.err != 0 {
+                return Err(Error::InvalidEncoding);
+            }
+            if self.out_len == 0 {
+                return Ok(0);
+            }
+        }
+
+        let n = (self.out_len - self.out_pos).min(out.len());
+        out[..n].copy_from_slice(&self.out_buf[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+
+        Ok(n)
+    }
+
+    /// Pull encoded input until a full quad (or source exhaustion) is
+    /// buffered, decode it, and stash the result in `out_buf` for
+    /// [`Decoder::decode`] to hand out a piece at a time.
+    fn fill_group(&mut self) -> Result<(), Error> {
+        while self.in_len < 4 && !self.eof {
+            let mut tmp = [0u8; 4];
+            let want = 4 - self.in_len;
+            let n = self.source.read_encoded(&mut tmp[..want])?;
+            if n == 0 {
+                self.eof = true;
+                break;
+            }
+            self.in_buf[self.in_len..self.in_len + n].copy_from_slice(&tmp[..n]);
+            self.in_len += n;
+        }
+
+        if self.in_len == 0 {
+            self.out_len = 0;
+            self.out_pos = 0;
+            return Ok(());
+        }
+
+        if self.in_len < 4 {
+            self.err |= !(self.in_len == 2 || self.in_len == 3) as i16;
+            let mut tmp_in = [b'A'; 4];
+            tmp_in[..self.in_len].copy_from_slice(&self.in_buf[..self.in_len]);
+            let mut tmp_out = [0u8; 3];
+            self.err |= decode_3bytes::<A>(&tmp_in, &mut tmp_out);
+            let n = (self.in_len * 3) / 4;
+            self.out_buf[..n].copy_from_slice(&tmp_out[..n]);
+            self.out_len = n;
+            self.out_pos = 0;
+            self.in_len = 0;
+            return Ok(());
+        }
+
+        self.err |= decode_3bytes::<A>(&self.in_buf, &mut self.out_buf);
+        self.out_len = 3;
+        self.out_pos = 0;
+        self.in_len = 0;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'r, R: Source, A: Alphabet> io::Read for Decoder<'r, R, A> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decode(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid B64 in decoder stream"))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+    use std::vec;
+    use std::vec::Vec;
+
+    fn encode_all(input: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        let mut enc = Encoder::<_, Std>::new(&mut encoded);
+        enc.update(input).unwrap();
+        enc.finish().unwrap();
+        encoded
+    }
+
+    #[test]
+    fn roundtrip_encoder_decoder() {
+        let input: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let encoded = encode_all(&input);
+
+        let mut cursor = Cursor::new(&encoded);
+        let mut dec = Decoder::<_, Std>::new(&mut cursor);
+        let mut out = Vec::new();
+        dec.read_to_end(&mut out).unwrap();
+        assert_eq!(out, input);
+    }
+
+    #[test]
+    fn decode_with_one_byte_buffer_does_not_lose_data() {
+        let input: [u8; 5] = [2, 19, 255, 0, 7];
+        let encoded = encode_all(&input);
+
+        let mut cursor = Cursor::new(&encoded);
+        let dec = Decoder::<_, Std>::new(&mut cursor);
+        // `Read::bytes` on an unbuffered reader reads one byte per call.
+        let out: Vec<u8> = dec.bytes().collect::<Result<_, _>>().unwrap();
+        assert_eq!(out, input);
+    }
+
+    #[test]
+    fn decode_with_odd_buffer_sizes() {
+        let input: [u8; 17] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let encoded = encode_all(&input);
+
+        for out_len in 1..5 {
+            let mut cursor = Cursor::new(&encoded);
+            let mut dec = Decoder::<_, Std>::new(&mut cursor);
+            let mut out = Vec::new();
+            let mut buf = vec![0u8; out_len];
+            loop {
+                let n = dec.decode(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                out.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(out, input, "out_len={out_len}");
+        }
+    }
+}